@@ -6,12 +6,12 @@
 
 use crate::private::BaseMetricId;
 use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 #[cfg(not(feature = "with_gecko"))]
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Mutex;
 #[cfg(feature = "with_gecko")]
 use {std::convert::TryInto, std::sync::atomic::AtomicU32, xpcom::interfaces::nsIXULRuntime};
 
@@ -35,46 +35,146 @@ pub struct IPCPayload {
     pub memory_samples: HashMap<BaseMetricId, Vec<u64>>,
     pub labeled_memory_samples: HashMap<BaseMetricId, HashMap<String, Vec<u64>>>,
     pub numerators: HashMap<BaseMetricId, i32>,
+    pub quantities: HashMap<BaseMetricId, i64>,
     pub rates: HashMap<BaseMetricId, (i32, i32)>,
     pub string_lists: HashMap<BaseMetricId, Vec<String>>,
+    pub texts: HashMap<BaseMetricId, String>,
     pub timing_samples: HashMap<BaseMetricId, Vec<u64>>,
     pub labeled_timing_samples: HashMap<BaseMetricId, HashMap<String, Vec<u64>>>,
+    /// Object metrics, carried as their serialized JSON payload keyed by id.
+    pub objects: HashMap<BaseMetricId, String>,
+}
+
+/// Magic tag prefixing every serialized IPC payload, so a buffer from an
+/// unrelated source (or an empty/garbage buffer) is rejected before we even
+/// look at the version.
+const IPC_PAYLOAD_MAGIC: [u8; 4] = *b"FOGI";
+
+/// Version of the serialized `IPCPayload` envelope.
+///
+/// Bump this whenever the bincode layout of `IPCPayload` changes in a way that
+/// a mismatched build could mis-deserialize: adding/removing/reordering fields,
+/// or changing the meaning of `BaseMetricId` numbering. `replay_from_buf`
+/// refuses to decode a blob written by a different version rather than risk
+/// mapping values onto the wrong metrics (staged rollouts, partial updates).
+const IPC_PAYLOAD_SCHEMA_VERSION: u32 = 1;
+
+/// The self-describing header written ahead of the bincode blob by `take_buf`
+/// and validated by `replay_from_buf`.
+#[derive(Debug, Deserialize, Serialize)]
+struct IPCPayloadHeader {
+    magic: [u8; 4],
+    version: u32,
+}
+
+impl Default for IPCPayloadHeader {
+    fn default() -> Self {
+        IPCPayloadHeader {
+            magic: IPC_PAYLOAD_MAGIC,
+            version: IPC_PAYLOAD_SCHEMA_VERSION,
+        }
+    }
+}
+
+/// Why a buffer handed to `replay_from_buf` could not be applied.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The buffer was too short, lacked the magic tag, or the bincode blob
+    /// itself failed to deserialize.
+    Malformed,
+    /// The buffer was written by an incompatible `IPCPayload` schema version.
+    /// The parent can count these instead of corrupting metrics.
+    VersionMismatch { ours: u32, theirs: u32 },
 }
 
 /// Global singleton: pending IPC payload.
+///
+/// A `parking_lot::Mutex` rather than `std::sync::Mutex`: it does not poison, so
+/// a panic inside a `with_ipc_payload` closure (e.g. deep in a metric's
+/// `accumulate`/`set`) no longer wedges every subsequent IPC recording for the
+/// life of the process.
 static PAYLOAD: Lazy<Mutex<IPCPayload>> = Lazy::new(|| Mutex::new(IPCPayload::default()));
-/// Global singleton: number of times the IPC payload was accessed.
-static PAYLOAD_ACCESS_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// Global singleton: running estimate of the pending payload's serialized size, in bytes.
+///
+/// This is an *estimate* maintained by the mutation closures (see
+/// [`with_ipc_payload`]), not an exact bincode measurement: exact measurement
+/// would mean re-serializing the whole payload on every access. Over- or
+/// under-counting only affects how eagerly we flush, never correctness, since
+/// [`take_buf`] re-serializes from scratch.
+static PAYLOAD_SIZE_ESTIMATE: AtomicUsize = AtomicUsize::new(0);
 
 // The maximum size of an IPC message in Firefox Desktop is 256MB.
 // (See IPC::Channel::kMaximumMessageSize)
-// In `IPCPayload` the largest size can be attained in the fewest accesses via events.
-// Each event could be its own u64 id, u64 timestamp, and HashMap of ten i32 to ten 100-byte strings.
-// That's 1056B = 8 + 8 + 10(4 + 100)
-// In 256MB we can fit 254200 or so of these, not counting overhead.
-// Let's take a conservative estimate of 100000 to
-// 0) Account for overhead
-// 1) Not be greedy
-// 2) Allow time for the dispatch to main thread which will actually perform the flush
-// "Why the -1?" Because fetch_add returns the value before the addition.
-// bug 1936851 - Perhaps due to longer and more event extras, or object and text metrics,
-//               we're hitting the size limit before hitting the watermark.
-//               Change the watermark from 100k - 1 to 90k - 1.
-const PAYLOAD_ACCESS_WATERMARK: usize = 90000 - 1;
+// We flush well before that to leave headroom for bincode bookkeeping and the
+// IPC framing that wraps the serialized blob, and to allow time for the
+// dispatch to the main thread which will actually perform the flush.
+//
+// bug 1936851 - We used to trigger the flush off a raw *access count*
+//               (PAYLOAD_ACCESS_WATERMARK), tuned down from 100k-1 to 90k-1
+//               because variable-size event extras (and object/text metrics)
+//               blew past kMaximumMessageSize before the count fired. Counting
+//               bytes directly removes that guesswork.
+const PAYLOAD_SIZE_WATERMARK: usize = 200 * 1024 * 1024;
+
+/// Estimated serialized-size contributions for the various metric shapes.
+///
+/// Child-process metric code reports these into the running size estimate via
+/// the byte count it returns from its [`with_ipc_payload`] closure. The numbers
+/// mirror bincode's on-the-wire layout closely enough to drive the flush; they
+/// are deliberately a little generous so we flush early rather than late.
+pub mod size_estimate {
+    use super::EventRecord;
+
+    /// Fixed per-entry overhead for a `HashMap<BaseMetricId, _>` slot: the
+    /// 4-byte id plus bincode's length/tag bookkeeping, rounded up.
+    const ENTRY_OVERHEAD: usize = 8;
+
+    /// A scalar value occupying a single map slot (boolean, counter, ...).
+    pub fn scalar() -> usize {
+        ENTRY_OVERHEAD + 8
+    }
+
+    /// A vector of fixed-width samples (distributions), `8 * len` bytes of data.
+    pub fn samples(len: usize) -> usize {
+        ENTRY_OVERHEAD + 8 * len
+    }
+
+    /// A single string value (text metrics, string-list entries).
+    pub fn string(s: &str) -> usize {
+        ENTRY_OVERHEAD + s.len()
+    }
+
+    /// A single event record: `8` id + `8` timestamp + the extras map.
+    pub fn event(record: &EventRecord) -> usize {
+        let (_, extra) = record;
+        8 + 8
+            + extra
+                .iter()
+                .map(|(k, v)| k.len() + v.len() + ENTRY_OVERHEAD)
+                .sum::<usize>()
+    }
+}
 
 pub fn with_ipc_payload<F, R>(f: F) -> R
 where
-    F: FnOnce(&mut IPCPayload) -> R,
+    F: FnOnce(&mut IPCPayload) -> (R, usize),
 {
-    if PAYLOAD_ACCESS_COUNT.fetch_add(1, Ordering::SeqCst) > PAYLOAD_ACCESS_WATERMARK {
-        // We reset this before the actual flush to keep all the logic together.
-        // Otherwise the count reset would need to happen down in take_buf().
-        // This may overcount (resulting in undersized payloads) which is okay.
-        PAYLOAD_ACCESS_COUNT.store(0, Ordering::SeqCst);
+    let (result, added_bytes) = {
+        let mut payload = PAYLOAD.lock();
+        f(&mut payload)
+    };
+    // We accumulate the reported bytes and flush once the estimate crosses the
+    // watermark. fetch_add returns the pre-addition value, so add it back in to
+    // compare against the up-to-date estimate.
+    let estimate = PAYLOAD_SIZE_ESTIMATE.fetch_add(added_bytes, Ordering::SeqCst) + added_bytes;
+    if estimate > PAYLOAD_SIZE_WATERMARK {
+        // Reset before the flush to keep the accounting in one place; take_buf
+        // zeroes it again when it drains. Over-resetting only undersizes the
+        // next payload, which is harmless.
+        PAYLOAD_SIZE_ESTIMATE.store(0, Ordering::SeqCst);
         handle_payload_filling();
     }
-    let mut payload = PAYLOAD.lock().unwrap();
-    f(&mut payload)
+    result
 }
 
 /// Do we need IPC?
@@ -189,13 +289,336 @@ pub fn need_ipc() -> bool {
     TEST_NEED_IPC.load(Ordering::Relaxed)
 }
 
+/// Serialize one `IPCPayload` prefixed with the versioned, self-describing
+/// header so a mismatched parent can detect an incompatible payload rather than
+/// mis-mapping ids. See `IPC_PAYLOAD_SCHEMA_VERSION`.
+fn serialize_with_header(payload: &IPCPayload) -> Option<Vec<u8>> {
+    bincode::serialize(&IPCPayloadHeader::default())
+        .and_then(|mut buf| {
+            bincode::serialize(payload).map(|body| {
+                buf.extend_from_slice(&body);
+                buf
+            })
+        })
+        .ok()
+}
+
+/// Greedily packs the entries of a drained `IPCPayload` into as many
+/// sub-payloads as it takes to keep each serialized buffer under `max_bytes`.
+///
+/// Whole metric entries are moved into the current sub-payload until the next
+/// one wouldn't fit; a single entry too large on its own (e.g. one metric's
+/// giant sample vector) is split across sub-payloads. Because `replay_from_buf`
+/// is additive (counters `add`, distributions `accumulate_samples`, events
+/// append), replaying the chunks in any order reconstructs the same aggregate.
+struct PayloadChunker {
+    max_bytes: usize,
+    /// Serialized size of an empty, header-only payload; the floor every
+    /// sub-payload starts from.
+    base_bytes: usize,
+    current: IPCPayload,
+    current_bytes: usize,
+    finished: Vec<Vec<u8>>,
+}
+
+impl PayloadChunker {
+    fn new(max_bytes: usize) -> Self {
+        let base_bytes = serialize_with_header(&IPCPayload::default()).map_or(0, |b| b.len());
+        PayloadChunker {
+            max_bytes,
+            base_bytes,
+            current: IPCPayload::default(),
+            current_bytes: base_bytes,
+            finished: Vec::new(),
+        }
+    }
+
+    /// Flush the current sub-payload first if adding `add` bytes would overflow
+    /// it, then reserve the space.
+    fn ensure_room(&mut self, add: usize) {
+        if self.current_bytes > self.base_bytes && self.current_bytes + add > self.max_bytes {
+            self.flush();
+        }
+        self.current_bytes += add;
+    }
+
+    fn flush(&mut self) {
+        if self.current_bytes <= self.base_bytes {
+            // Nothing but the empty envelope; don't emit a useless chunk.
+            return;
+        }
+        if let Some(buf) = serialize_with_header(&self.current) {
+            self.finished.push(buf);
+        }
+        self.current = IPCPayload::default();
+        self.current_bytes = self.base_bytes;
+    }
+
+    /// Place a whole, indivisible entry of the given estimated size.
+    fn put_whole(&mut self, size: usize, place: impl FnOnce(&mut IPCPayload)) {
+        self.ensure_room(size);
+        place(&mut self.current);
+    }
+
+    /// Place a collection-valued entry, splitting it across sub-payloads if it
+    /// is too large to fit in one. `size_of` estimates each element's
+    /// contribution; `insert` stores a sub-collection under `id`. When the
+    /// entry fits whole it packs alongside its neighbours; when it must split,
+    /// each piece is isolated in its own chunk so the shared `id` is never
+    /// overwritten within a single sub-payload.
+    fn put_collection<T>(
+        &mut self,
+        id: BaseMetricId,
+        items: Vec<T>,
+        size_of: impl Fn(&T) -> usize,
+        insert: impl Fn(&mut IPCPayload, BaseMetricId, Vec<T>),
+    ) {
+        let overhead = size_estimate::samples(0);
+        let total = overhead + items.iter().map(&size_of).sum::<usize>();
+        let budget = self.max_bytes.saturating_sub(self.base_bytes);
+        if total <= budget {
+            self.put_whole(total, move |p| insert(p, id, items));
+            return;
+        }
+        // Too big for any single chunk: split into pieces, each alone in a chunk.
+        self.flush();
+        let mut piece = Vec::new();
+        let mut piece_bytes = overhead;
+        for item in items {
+            let item_bytes = size_of(&item);
+            if !piece.is_empty() && piece_bytes + item_bytes > budget {
+                let taken = std::mem::take(&mut piece);
+                self.ensure_room(piece_bytes);
+                insert(&mut self.current, id, taken);
+                self.flush();
+                piece_bytes = overhead;
+            }
+            piece_bytes += item_bytes;
+            piece.push(item);
+        }
+        if !piece.is_empty() {
+            if piece_bytes > budget {
+                // A single element larger than a whole chunk. We can't split it
+                // further at this granularity; ship it oversized and warn.
+                log::warn!(
+                    "IPC metric entry {:?} has a single element exceeding max_bytes; shipping oversized",
+                    id
+                );
+            }
+            self.ensure_room(piece_bytes);
+            insert(&mut self.current, id, piece);
+            self.flush();
+        }
+    }
+
+    fn finish(mut self) -> Vec<Vec<u8>> {
+        self.flush();
+        self.finished
+    }
+}
+
+/// Drain the pending `IPCPayload` into one or more serialized buffers, each
+/// guaranteed (best-effort, see `PayloadChunker`) to stay under `max_bytes` so
+/// no single message exceeds `kMaximumMessageSize`.
+pub fn take_bufs(max_bytes: usize) -> Vec<Vec<u8>> {
+    let payload = with_ipc_payload(move |payload| {
+        let taken = std::mem::take(payload);
+        // We've drained the payload, so the size estimate starts fresh.
+        PAYLOAD_SIZE_ESTIMATE.store(0, Ordering::SeqCst);
+        (taken, 0)
+    });
+
+    let IPCPayload {
+        booleans,
+        labeled_booleans,
+        counters,
+        custom_samples,
+        labeled_custom_samples,
+        denominators,
+        events,
+        labeled_counters,
+        dual_labeled_counters,
+        memory_samples,
+        labeled_memory_samples,
+        numerators,
+        quantities,
+        rates,
+        string_lists,
+        texts,
+        timing_samples,
+        labeled_timing_samples,
+        objects,
+    } = payload;
+
+    let mut chunker = PayloadChunker::new(max_bytes);
+
+    for (id, value) in booleans {
+        chunker.put_whole(size_estimate::scalar(), move |p| {
+            p.booleans.insert(id, value);
+        });
+    }
+    for (id, value) in counters {
+        chunker.put_whole(size_estimate::scalar(), move |p| {
+            p.counters.insert(id, value);
+        });
+    }
+    for (id, value) in denominators {
+        chunker.put_whole(size_estimate::scalar(), move |p| {
+            p.denominators.insert(id, value);
+        });
+    }
+    for (id, value) in numerators {
+        chunker.put_whole(size_estimate::scalar(), move |p| {
+            p.numerators.insert(id, value);
+        });
+    }
+    for (id, value) in rates {
+        chunker.put_whole(size_estimate::scalar(), move |p| {
+            p.rates.insert(id, value);
+        });
+    }
+    for (id, value) in quantities {
+        chunker.put_whole(size_estimate::scalar(), move |p| {
+            p.quantities.insert(id, value);
+        });
+    }
+    // Text and object metrics carry a single (possibly large) string per id;
+    // last write wins, so they can't be split like the collection entries.
+    for (id, value) in texts {
+        chunker.put_whole(size_estimate::string(&value), move |p| {
+            p.texts.insert(id, value);
+        });
+    }
+    for (id, value) in objects {
+        chunker.put_whole(size_estimate::string(&value), move |p| {
+            p.objects.insert(id, value);
+        });
+    }
+
+    // Collection-valued entries, split across chunks when oversized.
+    for (id, samples) in custom_samples {
+        chunker.put_collection(id, samples, |_| 8, |p, id, v| {
+            p.custom_samples.insert(id, v);
+        });
+    }
+    for (id, samples) in memory_samples {
+        chunker.put_collection(id, samples, |_| 8, |p, id, v| {
+            p.memory_samples.insert(id, v);
+        });
+    }
+    for (id, samples) in timing_samples {
+        chunker.put_collection(id, samples, |_| 8, |p, id, v| {
+            p.timing_samples.insert(id, v);
+        });
+    }
+    for (id, strings) in string_lists {
+        chunker.put_collection(
+            id,
+            strings,
+            |s: &String| size_estimate::string(s),
+            |p, id, v| {
+                p.string_lists.insert(id, v);
+            },
+        );
+    }
+    for (id, records) in events {
+        chunker.put_collection(
+            id,
+            records,
+            size_estimate::event,
+            |p, id, v| {
+                p.events.insert(id, v);
+            },
+        );
+    }
+
+    // Labeled entries: split at label granularity, each sub-map in its own
+    // chunk when oversized. Replay merges them additively.
+    for (id, labeled) in labeled_booleans {
+        let items: Vec<(String, bool)> = labeled.into_iter().collect();
+        chunker.put_collection(
+            id,
+            items,
+            |(label, _)| size_estimate::string(label),
+            |p, id, v| {
+                p.labeled_booleans.insert(id, v.into_iter().collect());
+            },
+        );
+    }
+    for (id, labeled) in labeled_counters {
+        let items: Vec<(String, i32)> = labeled.into_iter().collect();
+        chunker.put_collection(
+            id,
+            items,
+            |(label, _)| size_estimate::string(label),
+            |p, id, v| {
+                p.labeled_counters.insert(id, v.into_iter().collect());
+            },
+        );
+    }
+    for (id, labeled) in dual_labeled_counters {
+        let items: Vec<((String, String), i32)> = labeled.into_iter().collect();
+        chunker.put_collection(
+            id,
+            items,
+            |((key, category), _)| size_estimate::string(key) + size_estimate::string(category),
+            |p, id, v| {
+                p.dual_labeled_counters.insert(id, v.into_iter().collect());
+            },
+        );
+    }
+    for (id, labeled) in labeled_custom_samples {
+        let items: Vec<(String, Vec<i64>)> = labeled.into_iter().collect();
+        chunker.put_collection(
+            id,
+            items,
+            |(label, samples)| size_estimate::string(label) + 8 * samples.len(),
+            |p, id, v| {
+                p.labeled_custom_samples.insert(id, v.into_iter().collect());
+            },
+        );
+    }
+    for (id, labeled) in labeled_memory_samples {
+        let items: Vec<(String, Vec<u64>)> = labeled.into_iter().collect();
+        chunker.put_collection(
+            id,
+            items,
+            |(label, samples)| size_estimate::string(label) + 8 * samples.len(),
+            |p, id, v| {
+                p.labeled_memory_samples.insert(id, v.into_iter().collect());
+            },
+        );
+    }
+    for (id, labeled) in labeled_timing_samples {
+        let items: Vec<(String, Vec<u64>)> = labeled.into_iter().collect();
+        chunker.put_collection(
+            id,
+            items,
+            |(label, samples)| size_estimate::string(label) + 8 * samples.len(),
+            |p, id, v| {
+                p.labeled_timing_samples.insert(id, v.into_iter().collect());
+            },
+        );
+    }
+
+    chunker.finish()
+}
+
+/// Drain the pending `IPCPayload` into a single serialized buffer.
+///
+/// A thin wrapper over `serialize_with_header` for callers that know their
+/// payload is small; callers that might overflow `kMaximumMessageSize` should
+/// use `take_bufs` instead.
 pub fn take_buf() -> Option<Vec<u8>> {
     with_ipc_payload(move |payload| {
-        let buf = bincode::serialize(&payload).ok();
+        let buf = serialize_with_header(payload);
         *payload = IPCPayload {
             ..Default::default()
         };
-        buf
+        // We've drained the payload, so the size estimate starts fresh. Report
+        // zero added bytes so draining never itself trips the watermark.
+        PAYLOAD_SIZE_ESTIMATE.store(0, Ordering::SeqCst);
+        (buf, 0)
     })
 }
 
@@ -229,238 +652,389 @@ pub fn is_in_automation() -> bool {
     unsafe { FOG_IPCIsInAutomation() }
 }
 
-// Reason: We instrument the error counts,
-// but don't need more detailed error information at the moment.
-#[allow(clippy::result_unit_err)]
-pub fn replay_from_buf(buf: &[u8]) -> Result<(), ()> {
-    // TODO: Instrument failures to find metrics by id.
-    let ipc_payload: IPCPayload = bincode::deserialize(buf).map_err(|_| ())?;
+/// Record that an id in a replayed `IPCPayload` could not be resolved to a
+/// metric, labeled by the metric type whose table we searched. Missing ids mean
+/// a value recorded off the main process is silently lost on the parent; the
+/// label lets operators see *which* metric shape is leaking and alert on it.
+/// `guard_replay` also counts here any entry whose replay panicked.
+fn record_replay_failure(metric_type: &str) {
+    #[cfg(feature = "with_gecko")]
+    crate::metrics::fog_ipc::replay_failures
+        .get(metric_type)
+        .add(1);
+    // Nothing to record against without Gecko's metric tables.
+    #[cfg(not(feature = "with_gecko"))]
+    let _ = metric_type;
+}
+
+/// Apply one replayed metric entry, containing any panic from deep inside a
+/// metric's `set`/`add`/`accumulate` so a single malformed entry is skipped
+/// (and counted) rather than unwinding through the rest of the replay. Now that
+/// `PAYLOAD` and the dynamic `__jog_metric_maps` locks are non-poisoning
+/// `parking_lot` locks, a contained panic here no longer leaves the IPC
+/// subsystem wedged for every later entry.
+fn guard_replay(metric_type: &str, apply: impl FnOnce()) {
+    if std::panic::catch_unwind(std::panic::AssertUnwindSafe(apply)).is_err() {
+        record_replay_failure(metric_type);
+    }
+}
+
+pub fn replay_from_buf(buf: &[u8]) -> Result<(), ReplayError> {
+    // Validate the self-describing header before touching the body, so a
+    // payload from a mismatched build is rejected with a distinct error
+    // instead of being mis-deserialized onto the wrong metrics.
+    let header_len = bincode::serialized_size(&IPCPayloadHeader::default())
+        .map_err(|_| ReplayError::Malformed)? as usize;
+    if buf.len() < header_len {
+        return Err(ReplayError::Malformed);
+    }
+    let (header_bytes, body_bytes) = buf.split_at(header_len);
+    let header: IPCPayloadHeader =
+        bincode::deserialize(header_bytes).map_err(|_| ReplayError::Malformed)?;
+    if header.magic != IPC_PAYLOAD_MAGIC {
+        return Err(ReplayError::Malformed);
+    }
+    if header.version != IPC_PAYLOAD_SCHEMA_VERSION {
+        // Don't risk mis-mapping ids across schema versions. Surface a distinct
+        // error so the parent can count incompatible payloads and move on.
+        log::warn!(
+            "Dropping IPC payload from incompatible schema version {} (ours is {})",
+            header.version,
+            IPC_PAYLOAD_SCHEMA_VERSION
+        );
+        return Err(ReplayError::VersionMismatch {
+            ours: IPC_PAYLOAD_SCHEMA_VERSION,
+            theirs: header.version,
+        });
+    }
+    let ipc_payload: IPCPayload =
+        bincode::deserialize(body_bytes).map_err(|_| ReplayError::Malformed)?;
     for (id, value) in ipc_payload.booleans.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::BOOLEAN_MAP
-                .read()
-                .expect("Read lock for dynamic boolean map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("boolean", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::BOOLEAN_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.set(value);
+                } else {
+                    record_replay_failure("boolean");
+                }
+            } else if let Some(metric) = __glean_metric_maps::BOOLEAN_MAP.get(&id) {
                 metric.set(value);
+            } else {
+                record_replay_failure("boolean");
             }
-        } else if let Some(metric) = __glean_metric_maps::BOOLEAN_MAP.get(&id) {
-            metric.set(value);
-        }
+        });
     }
     for (id, labeled_bools) in ipc_payload.labeled_booleans.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::LABELED_BOOLEAN_MAP
-                .read()
-                .expect("Read lock for dynamic labeled boolean map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("labeled_boolean", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::LABELED_BOOLEAN_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    for (label, value) in labeled_bools.into_iter() {
+                        metric.get(&label).set(value);
+                    }
+                } else {
+                    record_replay_failure("labeled_boolean");
+                }
+            } else {
                 for (label, value) in labeled_bools.into_iter() {
-                    metric.get(&label).set(value);
+                    __glean_metric_maps::labeled_boolean_get(*id, &label).set(value);
                 }
             }
-        } else {
-            for (label, value) in labeled_bools.into_iter() {
-                __glean_metric_maps::labeled_boolean_get(*id, &label).set(value);
-            }
-        }
+        });
     }
     for (id, value) in ipc_payload.counters.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::COUNTER_MAP
-                .read()
-                .expect("Read lock for dynamic counter map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("counter", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::COUNTER_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.add(value);
+                } else {
+                    record_replay_failure("counter");
+                }
+            } else if let Some(metric) = __glean_metric_maps::COUNTER_MAP.get(&id) {
                 metric.add(value);
+            } else {
+                record_replay_failure("counter");
             }
-        } else if let Some(metric) = __glean_metric_maps::COUNTER_MAP.get(&id) {
-            metric.add(value);
-        }
+        });
     }
     for (id, samples) in ipc_payload.custom_samples.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::CUSTOM_DISTRIBUTION_MAP
-                .read()
-                .expect("Read lock for dynamic custom distribution map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("custom_distribution", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::CUSTOM_DISTRIBUTION_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.accumulate_samples_signed(samples);
+                } else {
+                    record_replay_failure("custom_distribution");
+                }
+            } else if let Some(metric) = __glean_metric_maps::CUSTOM_DISTRIBUTION_MAP.get(&id) {
                 metric.accumulate_samples_signed(samples);
+            } else {
+                record_replay_failure("custom_distribution");
             }
-        } else if let Some(metric) = __glean_metric_maps::CUSTOM_DISTRIBUTION_MAP.get(&id) {
-            metric.accumulate_samples_signed(samples);
-        }
+        });
     }
     for (id, labeled_custom_samples) in ipc_payload.labeled_custom_samples.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::LABELED_CUSTOM_DISTRIBUTION_MAP
-                .read()
-                .expect("Read lock for dynamic labeled custom distribution map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("labeled_custom_distribution", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::LABELED_CUSTOM_DISTRIBUTION_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    for (label, samples) in labeled_custom_samples.into_iter() {
+                        metric.get(&label).accumulate_samples_signed(samples);
+                    }
+                } else {
+                    record_replay_failure("labeled_custom_distribution");
+                }
+            } else {
                 for (label, samples) in labeled_custom_samples.into_iter() {
-                    metric.get(&label).accumulate_samples_signed(samples);
+                    __glean_metric_maps::labeled_custom_distribution_get(*id, &label)
+                        .accumulate_samples_signed(samples);
                 }
             }
-        } else {
-            for (label, samples) in labeled_custom_samples.into_iter() {
-                __glean_metric_maps::labeled_custom_distribution_get(*id, &label)
-                    .accumulate_samples_signed(samples);
-            }
-        }
+        });
     }
     for (id, value) in ipc_payload.denominators.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::DENOMINATOR_MAP
-                .read()
-                .expect("Read lock for dynamic denominator map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("denominator", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::DENOMINATOR_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.add(value);
+                } else {
+                    record_replay_failure("denominator");
+                }
+            } else if let Some(metric) = __glean_metric_maps::DENOMINATOR_MAP.get(&id) {
                 metric.add(value);
+            } else {
+                record_replay_failure("denominator");
             }
-        } else if let Some(metric) = __glean_metric_maps::DENOMINATOR_MAP.get(&id) {
-            metric.add(value);
-        }
+        });
     }
     for (id, records) in ipc_payload.events.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::EVENT_MAP
-                .read()
-                .expect("Read lock for dynamic event map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("event", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::EVENT_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    for (timestamp, extra) in records.into_iter() {
+                        metric.record_with_time(timestamp, extra);
+                    }
+                } else {
+                    record_replay_failure("event");
+                }
+            } else {
                 for (timestamp, extra) in records.into_iter() {
-                    metric.record_with_time(timestamp, extra);
+                    if __glean_metric_maps::record_event_by_id_with_time(id, timestamp, extra).is_err() {
+                        record_replay_failure("event");
+                    }
                 }
             }
-        } else {
-            for (timestamp, extra) in records.into_iter() {
-                let _ = __glean_metric_maps::record_event_by_id_with_time(id, timestamp, extra);
-            }
-        }
+        });
     }
     for (id, labeled_counts) in ipc_payload.labeled_counters.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::LABELED_COUNTER_MAP
-                .read()
-                .expect("Read lock for dynamic labeled counter map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("labeled_counter", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::LABELED_COUNTER_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    for (label, count) in labeled_counts.into_iter() {
+                        metric.get(&label).add(count);
+                    }
+                } else {
+                    record_replay_failure("labeled_counter");
+                }
+            } else {
                 for (label, count) in labeled_counts.into_iter() {
-                    metric.get(&label).add(count);
+                    __glean_metric_maps::labeled_counter_get(*id, &label).add(count);
                 }
             }
-        } else {
-            for (label, count) in labeled_counts.into_iter() {
-                __glean_metric_maps::labeled_counter_get(*id, &label).add(count);
-            }
-        }
+        });
     }
     for (id, dual_labeled_counts) in ipc_payload.dual_labeled_counters.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::DUAL_LABELED_COUNTER_MAP
-                .read()
-                .expect("Read lock for dynamic dual labeled counter map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("dual_labeled_counter", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::DUAL_LABELED_COUNTER_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    for ((key, category), count) in dual_labeled_counts.into_iter() {
+                        metric.get(&key, &category).add(count);
+                    }
+                } else {
+                    record_replay_failure("dual_labeled_counter");
+                }
+            } else {
                 for ((key, category), count) in dual_labeled_counts.into_iter() {
-                    metric.get(&key, &category).add(count);
+                    __glean_metric_maps::dual_labeled_counter_get(*id, &key, &category).add(count);
                 }
             }
-        } else {
-            for ((key, category), count) in dual_labeled_counts.into_iter() {
-                __glean_metric_maps::dual_labeled_counter_get(*id, &key, &category).add(count);
-            }
-        }
+        });
     }
     for (id, samples) in ipc_payload.memory_samples.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::MEMORY_DISTRIBUTION_MAP
-                .read()
-                .expect("Read lock for dynamic memory dist map was poisoned");
-            if let Some(metric) = map.get(&id) {
-                metric.accumulate_samples(samples);
+        guard_replay("memory_distribution", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::MEMORY_DISTRIBUTION_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.accumulate_samples(samples);
+                } else {
+                    record_replay_failure("memory_distribution");
+                }
+            } else if let Some(metric) = __glean_metric_maps::MEMORY_DISTRIBUTION_MAP.get(&id) {
+                samples
+                    .into_iter()
+                    .for_each(|sample| metric.accumulate(sample));
+            } else {
+                record_replay_failure("memory_distribution");
             }
-        } else if let Some(metric) = __glean_metric_maps::MEMORY_DISTRIBUTION_MAP.get(&id) {
-            samples
-                .into_iter()
-                .for_each(|sample| metric.accumulate(sample));
-        }
+        });
     }
     for (id, labeled_memory_samples) in ipc_payload.labeled_memory_samples.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::LABELED_MEMORY_DISTRIBUTION_MAP
-                .read()
-                .expect("Read lock for dynamic labeled memory distribution map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("labeled_memory_distribution", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::LABELED_MEMORY_DISTRIBUTION_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    for (label, samples) in labeled_memory_samples.into_iter() {
+                        metric.get(&label).accumulate_samples(samples);
+                    }
+                } else {
+                    record_replay_failure("labeled_memory_distribution");
+                }
+            } else {
                 for (label, samples) in labeled_memory_samples.into_iter() {
-                    metric.get(&label).accumulate_samples(samples);
+                    __glean_metric_maps::labeled_memory_distribution_get(*id, &label)
+                        .accumulate_samples(samples);
                 }
             }
-        } else {
-            for (label, samples) in labeled_memory_samples.into_iter() {
-                __glean_metric_maps::labeled_memory_distribution_get(*id, &label)
-                    .accumulate_samples(samples);
-            }
-        }
+        });
     }
     for (id, value) in ipc_payload.numerators.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::NUMERATOR_MAP
-                .read()
-                .expect("Read lock for dynamic numerator map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("numerator", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::NUMERATOR_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.add_to_numerator(value);
+                } else {
+                    record_replay_failure("numerator");
+                }
+            } else if let Some(metric) = __glean_metric_maps::NUMERATOR_MAP.get(&id) {
                 metric.add_to_numerator(value);
+            } else {
+                record_replay_failure("numerator");
             }
-        } else if let Some(metric) = __glean_metric_maps::NUMERATOR_MAP.get(&id) {
-            metric.add_to_numerator(value);
-        }
+        });
+    }
+    for (id, value) in ipc_payload.quantities.into_iter() {
+        guard_replay("quantity", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::QUANTITY_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.set(value);
+                } else {
+                    record_replay_failure("quantity");
+                }
+            } else if let Some(metric) = __glean_metric_maps::QUANTITY_MAP.get(&id) {
+                metric.set(value);
+            } else {
+                record_replay_failure("quantity");
+            }
+        });
     }
     for (id, (n, d)) in ipc_payload.rates.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::RATE_MAP
-                .read()
-                .expect("Read lock for dynamic rate map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("rate", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::RATE_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.add_to_numerator(n);
+                    metric.add_to_denominator(d);
+                } else {
+                    record_replay_failure("rate");
+                }
+            } else if let Some(metric) = __glean_metric_maps::RATE_MAP.get(&id) {
                 metric.add_to_numerator(n);
                 metric.add_to_denominator(d);
+            } else {
+                record_replay_failure("rate");
             }
-        } else if let Some(metric) = __glean_metric_maps::RATE_MAP.get(&id) {
-            metric.add_to_numerator(n);
-            metric.add_to_denominator(d);
-        }
+        });
     }
     for (id, strings) in ipc_payload.string_lists.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::STRING_LIST_MAP
-                .read()
-                .expect("Read lock for dynamic string list map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("string_list", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::STRING_LIST_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    strings.iter().for_each(|s| metric.add(s));
+                } else {
+                    record_replay_failure("string_list");
+                }
+            } else if let Some(metric) = __glean_metric_maps::STRING_LIST_MAP.get(&id) {
                 strings.iter().for_each(|s| metric.add(s));
+            } else {
+                record_replay_failure("string_list");
             }
-        } else if let Some(metric) = __glean_metric_maps::STRING_LIST_MAP.get(&id) {
-            strings.iter().for_each(|s| metric.add(s));
-        }
+        });
+    }
+    for (id, value) in ipc_payload.texts.into_iter() {
+        guard_replay("text", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::TEXT_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.set(value);
+                } else {
+                    record_replay_failure("text");
+                }
+            } else if let Some(metric) = __glean_metric_maps::TEXT_MAP.get(&id) {
+                metric.set(value);
+            } else {
+                record_replay_failure("text");
+            }
+        });
     }
     for (id, samples) in ipc_payload.timing_samples.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::TIMING_DISTRIBUTION_MAP
-                .read()
-                .expect("Read lock for dynamic timing distribution map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("timing_distribution", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::TIMING_DISTRIBUTION_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.accumulate_raw_samples_nanos(samples);
+                } else {
+                    record_replay_failure("timing_distribution");
+                }
+            } else if let Some(metric) = __glean_metric_maps::TIMING_DISTRIBUTION_MAP.get(&id) {
                 metric.accumulate_raw_samples_nanos(samples);
+            } else {
+                record_replay_failure("timing_distribution");
             }
-        } else if let Some(metric) = __glean_metric_maps::TIMING_DISTRIBUTION_MAP.get(&id) {
-            metric.accumulate_raw_samples_nanos(samples);
-        }
+        });
     }
     for (id, labeled_timing_samples) in ipc_payload.labeled_timing_samples.into_iter() {
-        if id.is_dynamic() {
-            let map = crate::factory::__jog_metric_maps::LABELED_TIMING_DISTRIBUTION_MAP
-                .read()
-                .expect("Read lock for dynamic labeled timing distribution map was poisoned");
-            if let Some(metric) = map.get(&id) {
+        guard_replay("labeled_timing_distribution", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::LABELED_TIMING_DISTRIBUTION_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    for (label, samples) in labeled_timing_samples.into_iter() {
+                        metric.get(&label).accumulate_raw_samples_nanos(samples);
+                    }
+                } else {
+                    record_replay_failure("labeled_timing_distribution");
+                }
+            } else {
                 for (label, samples) in labeled_timing_samples.into_iter() {
-                    metric.get(&label).accumulate_raw_samples_nanos(samples);
+                    __glean_metric_maps::labeled_timing_distribution_get(*id, &label)
+                        .accumulate_raw_samples_nanos(samples);
                 }
             }
-        } else {
-            for (label, samples) in labeled_timing_samples.into_iter() {
-                __glean_metric_maps::labeled_timing_distribution_get(*id, &label)
-                    .accumulate_raw_samples_nanos(samples);
+        });
+    }
+    for (id, json) in ipc_payload.objects.into_iter() {
+        guard_replay("object", || {
+            if id.is_dynamic() {
+                let map = crate::factory::__jog_metric_maps::OBJECT_MAP.read();
+                if let Some(metric) = map.get(&id) {
+                    metric.set_from_str(&json);
+                } else {
+                    record_replay_failure("object");
+                }
+            } else if let Some(metric) = __glean_metric_maps::OBJECT_MAP.get(&id) {
+                metric.set_from_str(&json);
+            } else {
+                record_replay_failure("object");
             }
-        }
+        });
     }
     Ok(())
 }